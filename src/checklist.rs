@@ -76,5 +76,5 @@ pub fn parse_checklist(checklist_string: &str, verbose: bool) -> Result<Vec<Vert
         }
     }
 
-    return Ok(nodes);
+    Ok(nodes)
 }
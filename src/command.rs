@@ -0,0 +1,169 @@
+use anyhow::{Context, Result};
+use log::info;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::os::unix::process::CommandExt;
+use std::process::{Child, Command, Stdio};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const DEFAULT_BACKOFF: Duration = Duration::from_secs(1);
+
+/// Execution settings for a `run[...]` node. Unset fields fall back to the
+/// defaults from the config file.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CommandOptions {
+    pub timeout: Option<Duration>,
+    pub retries: Option<u32>,
+    pub backoff: Option<Duration>, // delay before the first retry, doubled for each one after
+    pub cwd: Option<String>,
+    pub env: Vec<(String, String)>,
+}
+
+impl CommandOptions {
+    pub fn or(&self, defaults: &CommandOptions) -> CommandOptions {
+        let mut env = defaults.env.clone();
+        env.extend(self.env.iter().cloned());
+        CommandOptions {
+            timeout: self.timeout.or(defaults.timeout),
+            retries: self.retries.or(defaults.retries),
+            backoff: self.backoff.or(defaults.backoff),
+            cwd: self.cwd.clone().or(defaults.cwd.clone()),
+            env,
+        }
+    }
+}
+
+pub struct CommandOutput {
+    pub code: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+    pub duration: Duration,
+    pub timed_out: bool,
+}
+
+impl CommandOutput {
+    pub fn success(&self) -> bool {
+        self.code == Some(0) && !self.timed_out
+    }
+
+    pub fn exit_text(&self) -> String {
+        match (self.timed_out, self.code) {
+            (true, _) => String::from("a timeout"),
+            (false, Some(code)) => format!("exit code {}", code),
+            (false, None) => String::from("a signal"),
+        }
+    }
+}
+
+/// Runs a command, retrying with backoff until it succeeds or runs out of
+/// retries. Every attempt is logged with its duration and exit code.
+pub fn run_command_with_retries(command: &str, options: &CommandOptions) -> Result<CommandOutput> {
+    let attempts = options.retries.unwrap_or(0) + 1;
+    let mut backoff = options.backoff.unwrap_or(DEFAULT_BACKOFF);
+    let mut attempt = 1;
+    loop {
+        let output = run_command(command, options)?;
+        info!(
+            "attempt {} of {} for {} finished with {} after {:.1?}",
+            attempt,
+            attempts,
+            command,
+            output.exit_text(),
+            output.duration
+        );
+        if output.success() || attempt >= attempts {
+            return Ok(output);
+        }
+        println!(
+            "Attempt {} of {} failed with {}, retrying in {:?}",
+            attempt,
+            attempts,
+            output.exit_text(),
+            backoff
+        );
+        thread::sleep(backoff);
+        backoff *= 2;
+        attempt += 1;
+    }
+}
+
+/// Runs a command through `sh -c`, echoing its output to the terminal as it
+/// arrives while also collecting it. A command that outlives its timeout is
+/// killed along with anything it started.
+pub fn run_command(command: &str, options: &CommandOptions) -> Result<CommandOutput> {
+    let start = Instant::now();
+    let mut shell = Command::new("sh");
+    shell
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(cwd) = &options.cwd {
+        shell.current_dir(cwd);
+    }
+    for (name, value) in &options.env {
+        shell.env(name, value);
+    }
+    if options.timeout.is_some() {
+        // its own process group, so a timeout can kill the whole pipeline
+        shell.process_group(0);
+    }
+    let mut child = shell
+        .spawn()
+        .with_context(|| format!("unable to start {}", command))?;
+    let stdout = tee(child.stdout.take(), false);
+    let stderr = tee(child.stderr.take(), true);
+    let (code, timed_out) = match options.timeout {
+        Some(timeout) => wait_with_timeout(&mut child, timeout)?,
+        None => (child.wait()?.code(), false),
+    };
+    Ok(CommandOutput {
+        code,
+        stdout: stdout.join().unwrap_or_default(),
+        stderr: stderr.join().unwrap_or_default(),
+        duration: start.elapsed(),
+        timed_out,
+    })
+}
+
+fn wait_with_timeout(child: &mut Child, timeout: Duration) -> Result<(Option<i32>, bool)> {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((status.code(), false));
+        }
+        if Instant::now() >= deadline {
+            let _ = Command::new("kill")
+                .arg("-KILL")
+                .arg(format!("-{}", child.id()))
+                .status();
+            let _ = child.kill();
+            let status = child.wait()?;
+            return Ok((status.code(), true));
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+fn tee<R: Read + Send + 'static>(pipe: Option<R>, to_stderr: bool) -> JoinHandle<String> {
+    thread::spawn(move || {
+        let mut collected = String::new();
+        if let Some(pipe) = pipe {
+            let mut reader = BufReader::new(pipe);
+            let mut line: Vec<u8> = Vec::new();
+            while reader.read_until(b'\n', &mut line).unwrap_or(0) > 0 {
+                let text = String::from_utf8_lossy(&line);
+                if to_stderr {
+                    eprint!("{}", text);
+                } else {
+                    print!("{}", text);
+                    let _ = std::io::stdout().flush();
+                }
+                collected.push_str(&text);
+                line.clear();
+            }
+        }
+        collected
+    })
+}
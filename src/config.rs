@@ -0,0 +1,122 @@
+use crate::command::CommandOptions;
+use crate::duration::parse_duration;
+use crate::vars::parse_assignment;
+use anyhow::{Context, Result};
+use pest::Parser as PestParser;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+#[derive(pest_derive::Parser)]
+#[grammar = "config.pest"]
+pub struct ConfigParser;
+
+/// Settings read from `~/.lanthir/config`, one `key = value` per line.
+/// Keys may be repeated where a setting takes several values, like `command.env`.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    entries: Vec<(String, String)>,
+}
+
+impl Config {
+    /// Loads the given config file, or `~/.lanthir/config` if it exists.
+    pub fn load(path: Option<PathBuf>) -> Result<Config> {
+        let path = match path {
+            Some(path) => path,
+            None => match default_config_path() {
+                Some(path) if path.is_file() => path,
+                _ => return Ok(Config::default()),
+            },
+        };
+        let config_string = fs::read_to_string(&path)
+            .with_context(|| format!("unable to read config file {}", path.display()))?;
+        parse_config(&config_string)
+            .with_context(|| format!("unable to parse config file {}", path.display()))
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.entries
+            .iter()
+            .rev()
+            .find(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn get_all(&self, key: &str) -> Vec<&str> {
+        self.entries
+            .iter()
+            .filter(|(entry_key, _)| entry_key == key)
+            .map(|(_, value)| value.as_str())
+            .collect()
+    }
+
+    pub fn get_duration(&self, key: &str) -> Result<Option<Duration>> {
+        match self.get(key) {
+            Some(value) => {
+                Ok(Some(parse_duration(value).with_context(|| {
+                    format!("invalid duration for {}", key)
+                })?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    pub fn get_u32(&self, key: &str) -> Result<Option<u32>> {
+        match self.get(key) {
+            Some(value) => Ok(Some(
+                value
+                    .parse()
+                    .with_context(|| format!("invalid number for {}", key))?,
+            )),
+            None => Ok(None),
+        }
+    }
+
+    /// Defaults for every `run[...]` node, overridden by the node's own options.
+    pub fn command_options(&self) -> Result<CommandOptions> {
+        let mut env: Vec<(String, String)> = Vec::new();
+        for assignment in self.get_all("command.env") {
+            env.push(parse_assignment(assignment).context("invalid command.env")?);
+        }
+        Ok(CommandOptions {
+            timeout: self.get_duration("command.timeout")?,
+            retries: self.get_u32("command.retries")?,
+            backoff: self.get_duration("command.backoff")?,
+            cwd: self.get("command.cwd").map(String::from),
+            env,
+        })
+    }
+}
+
+pub fn default_config_path() -> Option<PathBuf> {
+    home::home_dir().map(|mut path| {
+        path.push(".lanthir");
+        path.push("config");
+        path
+    })
+}
+
+pub fn parse_config(config_string: &str) -> Result<Config> {
+    let config_parts = ConfigParser::parse(Rule::config_file, config_string)?
+        .next()
+        .unwrap();
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for entry in config_parts.into_inner() {
+        match entry.as_rule() {
+            Rule::entry => {
+                let mut key = String::new();
+                let mut value = String::new();
+                for pair in entry.into_inner() {
+                    match pair.as_rule() {
+                        Rule::key => key = String::from(pair.as_str()),
+                        Rule::value => value = String::from(pair.as_str().trim()),
+                        _ => unreachable!(),
+                    }
+                }
+                entries.push((key, value));
+            }
+            _ => unreachable!(),
+        }
+    }
+    Ok(Config { entries })
+}
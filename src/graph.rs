@@ -1,17 +1,23 @@
+use crate::command::CommandOptions;
+use crate::guard::Guard;
 use std::fmt;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct Node {
     pub outputs: Vec<Edge>,
     pub label: Option<String>,
     pub cmd: Option<String>,
     pub cb: Option<String>,
+    pub capture: Option<String>, // variable to store the command's trimmed stdout in
+    pub cmd_options: CommandOptions,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct Edge {
     pub destination: String,
     pub label: Option<String>,
+    pub limit: Option<u32>,
+    pub guard: Option<Guard>,
 }
 
 impl fmt::Display for Edge {
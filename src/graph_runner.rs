@@ -1,19 +1,29 @@
+use crate::command::{run_command_with_retries, CommandOptions};
 use crate::graph::{Edge, Node};
+use crate::guard::{Condition, Guard};
+use crate::vars::Variables;
 use anyhow::{bail, Result};
 use arboard::Clipboard;
-use dialoguer::{theme::ColorfulTheme, FuzzySelect};
-use log::info;
+use dialoguer::{theme::ColorfulTheme, FuzzySelect, Input};
+use log::{error, info};
 use std::collections::HashMap;
 
 pub struct GraphMachine {
     current_node: String,
     graph: HashMap<String, Node>,
+    vars: Variables,
+    command_defaults: CommandOptions,
+    max_steps: Option<u32>,
+    steps: u32,
+    visits: HashMap<String, u32>,
+    traversals: HashMap<(String, String), u32>,
 }
 
 pub trait Traverse {
     fn run(&mut self) -> Result<()>;
-    fn choices(&self) -> Option<&Vec<Edge>>; // (edge label, destination)
+    fn choices(&self) -> Option<Vec<Edge>>; // edges that haven't reached their limit
     fn traverse(&mut self, destination: String);
+    fn visits(&self) -> u32;
     fn get_node_type(&self) -> Result<(NodeTextType, &str)>;
     fn get_node_label(&self) -> Option<&str>;
     fn get_command(&self) -> Option<&str>;
@@ -32,233 +42,332 @@ impl GraphMachine {
         GraphMachine {
             current_node,
             graph,
+            vars: Variables::new(),
+            command_defaults: CommandOptions::default(),
+            max_steps: None,
+            steps: 0,
+            visits: HashMap::new(),
+            traversals: HashMap::new(),
         }
     }
-}
 
-impl Traverse for GraphMachine {
-    fn run(&mut self) -> Result<()> {
-        info!("starting flowchart");
+    pub fn with_vars(mut self, vars: Variables) -> GraphMachine {
+        self.vars = vars;
+        self
+    }
+
+    pub fn with_command_defaults(mut self, command_defaults: CommandOptions) -> GraphMachine {
+        self.command_defaults = command_defaults;
+        self
+    }
+
+    pub fn with_max_steps(mut self, max_steps: Option<u32>) -> GraphMachine {
+        self.max_steps = max_steps;
+        self
+    }
+
+    fn visit(&mut self) -> Result<()> {
+        self.steps += 1;
+        if let Some(max_steps) = self.max_steps {
+            if self.steps > max_steps {
+                error!(
+                    "aborting at {}: exceeded the maximum of {} steps",
+                    &self.current_node, max_steps
+                );
+                bail!(
+                    "aborted at {} after exceeding the maximum of {} steps",
+                    &self.current_node,
+                    max_steps
+                );
+            }
+        }
+        let visits = self.visits.entry(self.current_node.clone()).or_insert(0);
+        *visits += 1;
+        if *visits > 1 {
+            info!("visiting {} (attempt {})", &self.current_node, visits);
+        }
+        Ok(())
+    }
+
+    fn prompt_text(&self, text: &str) -> String {
+        match self.visits() {
+            0 | 1 => String::from(text),
+            attempt => format!("{} (attempt {})", text, attempt),
+        }
+    }
+
+    /// Prompts the operator for the current node and carries out its copy or
+    /// command. Plain nodes with several ways out are answered by choosing an
+    /// edge instead, so they aren't prompted here.
+    fn process_node(&mut self, branching: bool) -> Result<String> {
         let process_node = &["Completed", "Skipping"];
         let cb_node = &["Copy to Clipboard", "Skip"];
         let cmd_node = &["Run Command", "Skip"];
-        loop {
-            match &self.choices() {
-                Some(choices) => {
-                    match choices.len() {
+        let (node_type, text) = self.get_node_type()?;
+        let text = self.vars.interpolate(text);
+        let prompt = self.prompt_text(&text);
+        match node_type {
+            NodeTextType::Cb => {
+                let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(&prompt)
+                    .default(0)
+                    .items(&cb_node[..])
+                    .interact()
+                    .unwrap();
+                match selection {
+                    0 => {
+                        let mut clipboard = Clipboard::new()?;
+                        clipboard.set_text(&text)?;
+                        println!("Copied to clipboard");
+                        info!("copied {} to clipboard at {}", &text, &self.current_node);
+                    }
+                    _ => {
+                        info!("skipped clipboard copy at {}", &self.current_node);
+                    }
+                };
+            }
+            NodeTextType::Cmd => {
+                let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(&prompt)
+                    .default(0)
+                    .items(&cmd_node[..])
+                    .interact()
+                    .unwrap();
+                match selection {
+                    0 => self.run_node_command(&text)?,
+                    _ => {
+                        self.vars.set("status", "skipped");
+                        info!("skipped running command at {}", &self.current_node);
+                    }
+                };
+            }
+            NodeTextType::Label | NodeTextType::Id => {
+                if !branching {
+                    let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                        .with_prompt(&prompt)
+                        .default(0)
+                        .items(&process_node[..])
+                        .interact()
+                        .unwrap();
+                    match selection {
                         0 => {
-                            unreachable!();
-                            // info!("dead end at {}", &self.current_node);
-                            // println!("no choices");
-                            // break;
-                        }
-                        1 => {
-                            let (node_type, text) = self.get_node_type()?;
-                            match node_type {
-                                NodeTextType::Cb => {
-                                    let selection =
-                                        FuzzySelect::with_theme(&ColorfulTheme::default())
-                                            .with_prompt(text)
-                                            .default(0)
-                                            .items(&cb_node[..])
-                                            .interact()
-                                            .unwrap();
-                                    match selection {
-                                        0 => {
-                                            let mut clipboard = Clipboard::new()?;
-                                            clipboard.set_text(text)?;
-                                            info!(
-                                                "copied {} to clipboard, proceeding from {} to {}",
-                                                text, &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                        _ => {
-                                            info!(
-                                                "skipped clipboard copy, going from {} to {}",
-                                                &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                    };
-                                    println!("Traversing to {}", &choices[0].destination);
-                                    self.traverse(choices[0].destination.clone());
-                                }
-                                NodeTextType::Cmd => {
-                                    let selection =
-                                        FuzzySelect::with_theme(&ColorfulTheme::default())
-                                            .with_prompt(text)
-                                            .default(0)
-                                            .items(&cmd_node[..])
-                                            .interact()
-                                            .unwrap();
-                                    match selection {
-                                        0 => {
-                                            println!(
-                                                "Running command (but not really yet): {}",
-                                                text
-                                            );
-                                            info!(
-                                                "ran command {}, proceeding from {} to {}",
-                                                text, &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                        _ => {
-                                            info!(
-                                                "skipped running command, going from {} to {}",
-                                                &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                    };
-                                    println!("Traversing to {}", &choices[0].destination);
-                                    self.traverse(choices[0].destination.clone());
-                                }
-                                NodeTextType::Label | NodeTextType::Id => {
-                                    let selection =
-                                        FuzzySelect::with_theme(&ColorfulTheme::default())
-                                            .with_prompt(text)
-                                            .default(0)
-                                            .items(&process_node[..])
-                                            .interact()
-                                            .unwrap();
-                                    match selection {
-                                        0 => {
-                                            info!(
-                                                "completed {} going to {}",
-                                                &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                        _ => {
-                                            info!(
-                                                "skipping {} going to {}",
-                                                &self.current_node, &choices[0].destination
-                                            );
-                                        }
-                                    };
-                                    println!("Traversing to {}", &choices[0].destination);
-                                    self.traverse(choices[0].destination.clone());
-                                }
-                            }
+                            info!("completed {}", &self.current_node);
                         }
                         _ => {
-                            let (node_type, text) = self.get_node_type()?;
-                            match node_type {
-                                NodeTextType::Cb => {
-                                    let selection =
-                                        FuzzySelect::with_theme(&ColorfulTheme::default())
-                                            .with_prompt(text)
-                                            .default(0)
-                                            .items(&cb_node[..])
-                                            .interact()
-                                            .unwrap();
-                                    match selection {
-                                        0 => {
-                                            let mut clipboard = Clipboard::new()?;
-                                            clipboard.set_text(text)?;
-                                            println!("Copied to clipboard");
-                                            info!(
-                                                "copied {} to clipboard at {}",
-                                                text, &self.current_node
-                                            );
-                                        }
-                                        _ => {
-                                            info!(
-                                                "skipped clipboard copy at {}",
-                                                &self.current_node
-                                            );
-                                        }
-                                    };
-                                }
-                                NodeTextType::Cmd => {
-                                    let selection =
-                                        FuzzySelect::with_theme(&ColorfulTheme::default())
-                                            .with_prompt(text)
-                                            .default(0)
-                                            .items(&cmd_node[..])
-                                            .interact()
-                                            .unwrap();
-                                    match selection {
-                                        0 => {
-                                            println!(
-                                                "Running command (but not really yet): {}",
-                                                text
-                                            );
-                                            info!("ran command {} at {}", text, &self.current_node);
-                                        }
-                                        _ => {
-                                            info!(
-                                                "skipped running command at {}",
-                                                &self.current_node
-                                            );
-                                        }
-                                    };
-                                }
-                                NodeTextType::Label | NodeTextType::Id => {}
-                            }
-                            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-                                .with_prompt(text)
-                                .default(0)
-                                .items(&choices[..])
-                                .interact()
-                                .unwrap();
-                            info!("at {} chose {}", &self.current_node, choices[selection]);
-                            self.traverse(choices[selection].destination.clone());
+                            info!("skipping {}", &self.current_node);
                         }
                     };
                 }
-                None => {
-                    let (node_type, text) = self.get_node_type()?;
-                    match node_type {
-                        NodeTextType::Cb => {
-                            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-                                .with_prompt(text)
-                                .default(0)
-                                .items(&cb_node[..])
-                                .interact()
-                                .unwrap();
-                            match selection {
-                                0 => {
-                                    let mut clipboard = Clipboard::new()?;
-                                    clipboard.set_text(text)?;
-                                    println!("Copied to clipboard");
-                                    info!("copied {} to clipboard at {}", text, &self.current_node);
-                                }
-                                _ => {
-                                    info!("skipped clipboard copy at {}", &self.current_node);
-                                }
-                            };
-                        }
-                        NodeTextType::Cmd => {
-                            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-                                .with_prompt(text)
-                                .default(0)
-                                .items(&cmd_node[..])
-                                .interact()
-                                .unwrap();
-                            match selection {
-                                0 => {
-                                    println!("Running command (but not really yet): {}", text);
-                                    info!("ran command {} at {}", text, &self.current_node);
-                                }
-                                _ => {
-                                    info!("skipped running command at {}", &self.current_node);
-                                }
-                            };
-                        }
-                        NodeTextType::Label | NodeTextType::Id => {
-                            let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
-                                .with_prompt(text)
-                                .default(0)
-                                .items(&process_node[..])
-                                .interact()
-                                .unwrap();
-                            match selection {
-                                0 => {
-                                    info!("completed {}", &self.current_node);
-                                }
-                                _ => {
-                                    info!("skipping {}", &self.current_node);
-                                }
-                            };
+            }
+        }
+        Ok(prompt)
+    }
+
+    fn run_node_command(&mut self, command: &str) -> Result<()> {
+        let node = self.graph.get(&self.current_node);
+        let capture = node.and_then(|node| node.capture.clone());
+        let mut options = match node {
+            Some(node) => node.cmd_options.or(&self.command_defaults),
+            None => self.command_defaults.clone(),
+        };
+        options.cwd = options.cwd.map(|cwd| self.vars.interpolate(&cwd));
+        for (_, value) in options.env.iter_mut() {
+            *value = self.vars.interpolate(value);
+        }
+        println!("Running command: {}", command);
+        let output = run_command_with_retries(command, &options)?;
+        info!(
+            "ran command {} at {}, finished with {} after {:.1?}",
+            command,
+            &self.current_node,
+            output.exit_text(),
+            output.duration
+        );
+        if !output.success() {
+            println!("Command failed with {}", output.exit_text());
+        }
+        let status = match (output.success(), output.timed_out) {
+            (true, _) => "success",
+            (false, true) => "timeout",
+            (false, false) => "failure",
+        };
+        self.vars.set("status", status);
+        match output.code {
+            Some(code) => self.vars.set("exit_code", &code.to_string()),
+            None => self.vars.set("exit_code", ""),
+        }
+        if let Some(variable) = capture {
+            let value = output.stdout.trim();
+            println!("Captured {} = {}", &variable, value);
+            info!("captured {} into {}", value, &variable);
+            self.vars.set(&variable, value);
+        }
+        Ok(())
+    }
+
+    fn choose(&mut self, choices: Vec<Edge>, prompt: &str) -> Result<String> {
+        let choices = self.eligible_choices(choices)?;
+        let destination = match choices.len() {
+            1 => {
+                println!("{}: following {}", prompt, &choices[0]);
+                info!("at {} guard chose {}", &self.current_node, choices[0]);
+                choices[0].destination.clone()
+            }
+            _ => {
+                let choice_items: Vec<String> =
+                    choices.iter().map(|edge| self.choice_text(edge)).collect();
+                let selection = FuzzySelect::with_theme(&ColorfulTheme::default())
+                    .with_prompt(prompt)
+                    .default(0)
+                    .items(&choice_items)
+                    .interact()
+                    .unwrap();
+                info!("at {} chose {}", &self.current_node, choices[selection]);
+                choices[selection].destination.clone()
+            }
+        };
+        Ok(destination)
+    }
+
+    /// Narrows the choices down to the edges whose guards hold, asking the
+    /// operator for any variable a guard needs that hasn't been set yet.
+    /// An edge labelled `else` is only eligible when no guard holds.
+    fn eligible_choices(&mut self, choices: Vec<Edge>) -> Result<Vec<Edge>> {
+        let guards: Vec<Option<Guard>> = choices
+            .iter()
+            .map(|edge| self.edge_guard(edge, &choices))
+            .collect();
+        if guards.iter().all(|guard| guard.is_none()) {
+            return Ok(choices);
+        }
+        for guard in guards.iter().flatten() {
+            for variable in guard.variables() {
+                if !self.vars.is_set(variable) {
+                    let value: String = Input::with_theme(&ColorfulTheme::default())
+                        .with_prompt(format!("Value for {}", variable))
+                        .allow_empty(true)
+                        .interact_text()
+                        .unwrap();
+                    info!("set {} to {} from operator input", variable, &value);
+                    self.vars.set(variable, &value);
+                }
+            }
+        }
+        let any_guard_holds = guards
+            .iter()
+            .flatten()
+            .any(|guard| guard.evaluate(&self.vars));
+        let eligible: Vec<Edge> = choices
+            .iter()
+            .zip(&guards)
+            .filter(|(edge, guard)| match guard {
+                Some(guard) => guard.evaluate(&self.vars),
+                None if edge.label.as_deref() == Some("else") => !any_guard_holds,
+                None => true,
+            })
+            .map(|(edge, _)| edge.clone())
+            .collect();
+        if eligible.is_empty() {
+            info!(
+                "no guard out of {} holds, offering every edge",
+                &self.current_node
+            );
+            println!("None of the conditions out of {} hold.", &self.current_node);
+            return Ok(choices);
+        }
+        Ok(eligible)
+    }
+
+    /// Edges out of a command node labelled `success`, `failure` or `timeout`
+    /// are guarded by how the command finished. `failure` covers a timeout too
+    /// unless there is a separate `timeout` edge.
+    fn edge_guard(&self, edge: &Edge, edges: &[Edge]) -> Option<Guard> {
+        if edge.guard.is_some() {
+            return edge.guard.clone();
+        }
+        self.get_command()?;
+        let status = |value: &str| {
+            vec![Condition::Equals(
+                String::from("status"),
+                String::from(value),
+            )]
+        };
+        match edge.label.as_deref() {
+            Some("success") => Some(Guard {
+                clauses: vec![status("success")],
+            }),
+            Some("timeout") => Some(Guard {
+                clauses: vec![status("timeout")],
+            }),
+            Some("failure") => {
+                let has_timeout_edge = edges
+                    .iter()
+                    .any(|edge| edge.label.as_deref() == Some("timeout"));
+                Some(Guard {
+                    clauses: match has_timeout_edge {
+                        true => vec![status("failure")],
+                        false => vec![status("failure"), status("timeout")],
+                    },
+                })
+            }
+            _ => None,
+        }
+    }
+
+    fn choice_text(&self, edge: &Edge) -> String {
+        match edge.limit {
+            Some(limit) => {
+                let key = (self.current_node.clone(), edge.destination.clone());
+                let taken = self.traversals.get(&key).copied().unwrap_or(0);
+                format!(
+                    "{} ({} of {} left)",
+                    edge,
+                    limit.saturating_sub(taken),
+                    limit
+                )
+            }
+            None => edge.to_string(),
+        }
+    }
+}
+
+impl Traverse for GraphMachine {
+    fn run(&mut self) -> Result<()> {
+        info!("starting flowchart");
+        loop {
+            self.visit()?;
+            let choices = self.choices();
+            let branching = match &choices {
+                Some(choices) => choices.len() > 1 || choices[0].guard.is_some(),
+                None => false,
+            };
+            let prompt = self.process_node(branching)?;
+            match choices {
+                Some(choices) => {
+                    let destination = match branching {
+                        true => self.choose(choices, &prompt)?,
+                        false => {
+                            info!(
+                                "going from {} to {}",
+                                &self.current_node, &choices[0].destination
+                            );
+                            println!("Traversing to {}", &choices[0].destination);
+                            choices[0].destination.clone()
                         }
+                    };
+                    self.traverse(destination);
+                }
+                None => {
+                    if self
+                        .graph
+                        .get(&self.current_node)
+                        .is_some_and(|node| !node.outputs.is_empty())
+                    {
+                        info!(
+                            "every edge out of {} has reached its limit",
+                            &self.current_node
+                        );
+                        println!("Every path out of {} has been used up.", &self.current_node);
                     }
                     info!("dead end at {}", &self.current_node);
                     println!("This is the end.");
@@ -270,38 +379,56 @@ impl Traverse for GraphMachine {
         Ok(())
     }
 
-    fn choices(&self) -> Option<&Vec<Edge>> {
+    fn choices(&self) -> Option<Vec<Edge>> {
         let edges = match self.graph.get(&self.current_node) {
             Some(node) => &node.outputs,
             None => return None,
         };
-        let choices = match edges.len() {
-            0 => return None,
-            _ => edges,
-        };
-        Some(choices)
+        let choices: Vec<Edge> = edges
+            .iter()
+            .filter(|edge| match edge.limit {
+                Some(limit) => {
+                    let key = (self.current_node.clone(), edge.destination.clone());
+                    self.traversals.get(&key).copied().unwrap_or(0) < limit
+                }
+                None => true,
+            })
+            .cloned()
+            .collect();
+        match choices.len() {
+            0 => None,
+            _ => Some(choices),
+        }
     }
 
     fn traverse(&mut self, destination: String) {
+        *self
+            .traversals
+            .entry((self.current_node.clone(), destination.clone()))
+            .or_insert(0) += 1;
         self.current_node = destination;
     }
 
+    fn visits(&self) -> u32 {
+        self.visits.get(&self.current_node).copied().unwrap_or(0)
+    }
+
     fn get_node_type(&self) -> Result<(NodeTextType, &str)> {
         match self.graph.get(&self.current_node) {
             Some(node) => {
                 if let Some(cb) = &node.cb {
-                    return Ok((NodeTextType::Cb, &cb));
+                    return Ok((NodeTextType::Cb, cb));
                 }
                 if let Some(cmd) = &node.cmd {
-                    return Ok((NodeTextType::Cmd, &cmd));
+                    return Ok((NodeTextType::Cmd, cmd));
                 }
                 if let Some(label) = &node.label {
-                    return Ok((NodeTextType::Label, &label));
+                    return Ok((NodeTextType::Label, label));
                 }
-                return Ok((NodeTextType::Id, &self.current_node));
+                Ok((NodeTextType::Id, &self.current_node))
             }
             None => bail!("no node corresponding wtih {}", &self.current_node),
-        };
+        }
     }
 
     fn get_node_label(&self) -> Option<&str> {
@@ -0,0 +1,63 @@
+use crate::vars::Variables;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    Equals(String, String),
+    NotEquals(String, String),
+    Truthy(String),
+    Falsy(String),
+}
+
+/// An edge guard such as `if env == prod and region != eu`.
+/// `and` binds tighter than `or`, so the guard is true when every condition
+/// of at least one clause holds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Guard {
+    pub clauses: Vec<Vec<Condition>>,
+}
+
+impl Condition {
+    pub fn variable(&self) -> &str {
+        match self {
+            Condition::Equals(variable, _)
+            | Condition::NotEquals(variable, _)
+            | Condition::Truthy(variable)
+            | Condition::Falsy(variable) => variable,
+        }
+    }
+
+    pub fn evaluate(&self, vars: &Variables) -> bool {
+        let value = vars.get(self.variable()).unwrap_or("");
+        match self {
+            Condition::Equals(_, expected) => value == expected,
+            Condition::NotEquals(_, expected) => value != expected,
+            Condition::Truthy(_) => is_truthy(value),
+            Condition::Falsy(_) => !is_truthy(value),
+        }
+    }
+}
+
+impl Guard {
+    pub fn variables(&self) -> Vec<&str> {
+        let mut variables: Vec<&str> = Vec::new();
+        for condition in self.clauses.iter().flatten() {
+            if !variables.contains(&condition.variable()) {
+                variables.push(condition.variable());
+            }
+        }
+        variables
+    }
+
+    pub fn evaluate(&self, vars: &Variables) -> bool {
+        self.clauses
+            .iter()
+            .any(|clause| clause.iter().all(|condition| condition.evaluate(vars)))
+    }
+}
+
+fn is_truthy(value: &str) -> bool {
+    !matches!(
+        value.to_lowercase().as_str(),
+        "" | "0" | "false" | "no" | "off"
+    )
+}
@@ -1,7 +1,12 @@
 pub mod checklist;
 pub mod checklist_runner;
+pub mod command;
+pub mod config;
+pub mod duration;
 pub mod graph;
 pub mod graph_runner;
+pub mod guard;
 pub mod mermaid;
 pub mod oats;
 pub mod oats_runner;
+pub mod vars;
@@ -8,16 +8,18 @@ use clap::Parser as ClapParser;
 use dialoguer::{theme::ColorfulTheme, FuzzySelect};
 use lanthir_cli::checklist::parse_checklist;
 use lanthir_cli::checklist_runner::{Checkify, ChecklistMachine};
+use lanthir_cli::config::Config;
 use lanthir_cli::graph_runner::{GraphMachine, Traverse};
 use lanthir_cli::mermaid::parse_mermaid;
 use lanthir_cli::oats::parse_oats;
 use lanthir_cli::oats_runner::{Oatify, OatsMachine};
+use lanthir_cli::vars::{parse_assignment, Variables};
 #[allow(unused_imports)]
 use pest::Parser as PestParser;
 use sha2::{Digest, Sha256};
 use simplelog::{ConfigBuilder, LevelFilter, WriteLogger};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(clap::Parser)]
 #[command(author, version, about, long_about = None)]
@@ -26,14 +28,21 @@ struct Cli {
     input: Option<PathBuf>,
     #[arg(long)]
     log_path: Option<PathBuf>,
+    #[arg(long)]
+    config: Option<PathBuf>,
     #[arg(short, long)]
     log: Option<bool>,
     #[arg(long)]
     verbose: bool,
+    #[arg(long)]
+    max_steps: Option<u32>,
+    #[arg(long = "var", value_name = "NAME=VALUE", value_parser = parse_assignment)]
+    vars: Vec<(String, String)>,
 }
 
 fn main() -> Result<()> {
     let args = Cli::parse();
+    let config = Config::load(args.config.clone())?;
 
     match args.input {
         Some(input) => match input.extension() {
@@ -45,8 +54,15 @@ fn main() -> Result<()> {
                         setup_logging(args.log, args.log_path, &input, &flowchart_string)?;
 
                         let flowchart_graph = parse_mermaid(&flowchart_string, args.verbose)?;
+                        let mut vars = Variables::new();
+                        for (name, value) in &args.vars {
+                            vars.set(name, value);
+                        }
                         let mut flowchart_runner =
-                            GraphMachine::new(String::from("Start"), flowchart_graph);
+                            GraphMachine::new(String::from("Start"), flowchart_graph)
+                                .with_vars(vars)
+                                .with_command_defaults(config.command_options()?)
+                                .with_max_steps(args.max_steps);
                         flowchart_runner.run()?;
                     }
 
@@ -114,7 +130,7 @@ fn filename_timestamp() -> Result<String> {
     Ok(timestamp)
 }
 
-fn setup_logging(log_flag: Option<bool>, custom_log_dir: Option<PathBuf>, input_filename: &PathBuf, input_file: &String ) -> Result<()> {
+fn setup_logging(log_flag: Option<bool>, custom_log_dir: Option<PathBuf>, input_filename: &Path, input_file: &String ) -> Result<()> {
     if log_flag.unwrap_or(true) {
         let log_path: Option<PathBuf> = match custom_log_dir {
             Some(mut path) => {
@@ -128,7 +144,7 @@ fn setup_logging(log_flag: Option<bool>, custom_log_dir: Option<PathBuf>, input_
 
                     let filename = generate_logfile_name(
                         input_filename.file_stem().unwrap().to_str().unwrap(),
-                        &input_file,
+                        input_file,
                     )?;
                     path.push(filename);
                     Some(path)
@@ -172,16 +188,13 @@ fn setup_logging(log_flag: Option<bool>, custom_log_dir: Option<PathBuf>, input_
         };
         
         if should_create_log_file {
-            match log_path {
-                Some(path) => {
-                    let config = ConfigBuilder::new().set_time_format_rfc3339().build();
-                    let _ = WriteLogger::init(
-                        LevelFilter::Info,
-                        config,
-                        fs::File::create(path).expect("the log dir should exist for the log file to be created in"),
-                    );
-                }
-                None => {}
+            if let Some(path) = log_path {
+                let config = ConfigBuilder::new().set_time_format_rfc3339().build();
+                let _ = WriteLogger::init(
+                    LevelFilter::Info,
+                    config,
+                    fs::File::create(path).expect("the log dir should exist for the log file to be created in"),
+                );
             }
         }
         
@@ -1,5 +1,9 @@
+use crate::command::CommandOptions;
+use crate::duration::parse_duration;
 use crate::graph::{Edge, Node};
+use crate::guard::{Condition, Guard};
 use anyhow::Result;
+use pest::iterators::Pair;
 use pest::Parser as PestParser;
 use std::collections::HashMap;
 
@@ -7,6 +11,11 @@ use std::collections::HashMap;
 #[grammar = "mermaid.pest"]
 pub struct MermaidParser;
 
+// (node id, node attributes) as declared on a single line
+type NodeDecl = (String, Node);
+// (is directed, edge without its destination)
+type EdgeDecl = (bool, Edge);
+
 pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<String, Node>> {
     let mermaid_parts = MermaidParser::parse(Rule::mmd, flowchart_string)
         .expect("unsuccessful pest parse")
@@ -17,10 +26,8 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
     for part in mermaid_parts.into_inner() {
         match part.as_rule() {
             Rule::line => {
-                let mut line_node_clusters: Vec<
-                    Vec<(String, Option<String>, Option<String>, Option<String>)>,
-                > = Vec::new();
-                let mut line_edges: Vec<(bool, Option<String>)> = Vec::new();
+                let mut line_node_clusters: Vec<Vec<NodeDecl>> = Vec::new();
+                let mut line_edges: Vec<EdgeDecl> = Vec::new();
                 // let mut node_index: u8 = 0;
                 // let mut edge_index: u8 = 0;
                 // Line;
@@ -28,12 +35,7 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                     match pair.as_rule() {
                         Rule::node_cluster => {
                             // Node Cluster
-                            let mut cluster_nodes: Vec<(
-                                String,
-                                Option<String>,
-                                Option<String>,
-                                Option<String>,
-                            )> = Vec::new();
+                            let mut cluster_nodes: Vec<NodeDecl> = Vec::new();
                             for node in pair.into_inner() {
                                 match node.as_rule() {
                                     Rule::node => {
@@ -42,6 +44,8 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                                         let mut node_text: Option<String> = None;
                                         let mut node_cmd: Option<String> = None;
                                         let mut node_cb: Option<String> = None;
+                                        let mut node_capture: Option<String> = None;
+                                        let mut node_cmd_options = CommandOptions::default();
                                         for node_attr in node.into_inner() {
                                             match node_attr.as_rule() {
                                                 Rule::node_id => {
@@ -80,6 +84,18 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                                                                                 command_string,
                                                                             );
                                                                         }
+                                                                        Rule::capture => {
+                                                                            node_capture =
+                                                                                parse_capture(
+                                                                                    shell_command,
+                                                                                );
+                                                                        }
+                                                                        Rule::cmd_option => {
+                                                                            parse_cmd_option(
+                                                                                shell_command,
+                                                                                &mut node_cmd_options,
+                                                                            )?;
+                                                                        }
                                                                         _ => unreachable!(),
                                                                     }
                                                                 }
@@ -121,7 +137,17 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                                                 _ => unreachable!(),
                                             }
                                         }
-                                        cluster_nodes.push((node_id, node_text, node_cmd, node_cb));
+                                        cluster_nodes.push((
+                                            node_id,
+                                            Node {
+                                                outputs: vec![],
+                                                label: node_text,
+                                                cmd: node_cmd,
+                                                cb: node_cb,
+                                                capture: node_capture,
+                                                cmd_options: node_cmd_options,
+                                            },
+                                        ));
                                     }
                                     _ => {
                                         unreachable!()
@@ -131,7 +157,7 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                             line_node_clusters.push(cluster_nodes);
                         }
                         Rule::edge => {
-                            let mut line_edge: Option<String> = None;
+                            let mut line_edge = Edge::default();
                             let mut edge_is_directed = false;
                             for edge in pair.into_inner() {
                                 match edge.as_rule() {
@@ -143,17 +169,15 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                                                     for edge_text in edge_part.into_inner() {
                                                         match edge_text.as_rule() {
                                                             Rule::edge_text => {
-                                                                line_edge = Some(String::from(
-                                                                    edge_text.as_str(),
-                                                                ));
+                                                                line_edge =
+                                                                    parse_edge_text(edge_text);
                                                             }
                                                             _ => unreachable!(),
                                                         }
                                                     }
                                                 }
                                                 Rule::edge_text => {
-                                                    line_edge =
-                                                        Some(String::from(edge_part.as_str()));
+                                                    line_edge = parse_edge_text(edge_part);
                                                 }
                                                 _ => unreachable!(),
                                             }
@@ -194,21 +218,14 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
                                     .or_insert(Node {
                                         outputs: vec![Edge {
                                             destination: dest_node.0.clone(),
-                                            label: line_edges[index].1.clone(),
+                                            ..line_edges[index].1.clone()
                                         }],
-                                        label: src_node.1.clone(),
-                                        cmd: src_node.2.clone(),
-                                        cb: src_node.3.clone(),
+                                        ..src_node.1.clone()
                                     });
                                 graph
                                     .entry(dest_node.0.clone())
                                     .and_modify(|entry| modify_dest_entry(entry, dest_node.clone()))
-                                    .or_insert(Node {
-                                        outputs: vec![],
-                                        label: dest_node.1.clone(),
-                                        cmd: dest_node.2.clone(),
-                                        cb: dest_node.3.clone(),
-                                    });
+                                    .or_insert(dest_node.1.clone());
                             }
                         }
                     }
@@ -222,39 +239,132 @@ pub fn parse_mermaid(flowchart_string: &str, verbose: bool) -> Result<HashMap<St
     Ok(graph)
 }
 
-fn modify_src_entry(
-    entry: &mut Node,
-    src_node: (String, Option<String>, Option<String>, Option<String>),
-    edge: (bool, Option<String>),
-    dest_node: (String, Option<String>, Option<String>, Option<String>),
-) {
-    entry.outputs.push(Edge {
-        destination: dest_node.0.clone(),
-        label: edge.1.clone(),
-    });
-    if src_node.1.is_some() {
-        // overwriting previous values to match mermaid :rolling-eyes:
-        entry.label = src_node.1;
+fn parse_edge_text(edge_text: Pair<Rule>) -> Edge {
+    let mut edge = Edge::default();
+    for edge_part in edge_text.into_inner() {
+        match edge_part.as_rule() {
+            Rule::edge_label => edge.label = Some(String::from(edge_part.as_str().trim())),
+            Rule::edge_limit => edge.limit = parse_edge_limit(edge_part),
+            Rule::edge_guard => {
+                let guard_start = edge_part.as_span().start();
+                let guard_text = edge_part.as_str();
+                let mut label_end = guard_text.len();
+                let mut clauses: Vec<Vec<Condition>> = Vec::new();
+                for guard_part in edge_part.into_inner() {
+                    match guard_part.as_rule() {
+                        Rule::guard_clause => clauses.push(parse_guard_clause(guard_part)),
+                        Rule::edge_limit => {
+                            label_end = guard_part.as_span().start() - guard_start;
+                            edge.limit = parse_edge_limit(guard_part);
+                        }
+                        Rule::guard_if | Rule::guard_or => {}
+                        _ => unreachable!(),
+                    }
+                }
+                edge.label = Some(String::from(guard_text[..label_end].trim()));
+                edge.guard = Some(Guard { clauses });
+            }
+            _ => unreachable!(),
+        }
     }
-    if src_node.2.is_some() {
-        entry.cmd = src_node.2;
+    edge
+}
+
+fn parse_edge_limit(edge_limit: Pair<Rule>) -> Option<u32> {
+    let mut limit: Option<u32> = None;
+    for limit_part in edge_limit.into_inner() {
+        match limit_part.as_rule() {
+            Rule::edge_limit_count => limit = limit_part.as_str().parse().ok(),
+            _ => unreachable!(),
+        }
     }
-    if src_node.3.is_some() {
-        entry.cb = src_node.3;
+    limit
+}
+
+fn parse_guard_clause(guard_clause: Pair<Rule>) -> Vec<Condition> {
+    let mut conditions: Vec<Condition> = Vec::new();
+    for condition in guard_clause.into_inner() {
+        match condition.as_rule() {
+            Rule::guard_condition => {
+                let mut negated = false;
+                let mut variable = String::new();
+                let mut operator = "";
+                let mut value = String::new();
+                for condition_part in condition.into_inner() {
+                    match condition_part.as_rule() {
+                        Rule::guard_not => negated = true,
+                        Rule::guard_variable => variable = String::from(condition_part.as_str()),
+                        Rule::guard_operator => operator = condition_part.as_str(),
+                        Rule::guard_value => value = String::from(condition_part.as_str()),
+                        _ => unreachable!(),
+                    }
+                }
+                conditions.push(match (operator, negated) {
+                    ("==", _) => Condition::Equals(variable, value),
+                    ("!=", _) => Condition::NotEquals(variable, value),
+                    (_, false) => Condition::Truthy(variable),
+                    (_, true) => Condition::Falsy(variable),
+                });
+            }
+            Rule::guard_and => {}
+            _ => unreachable!(),
+        }
+    }
+    conditions
+}
+
+fn parse_capture(capture: Pair<Rule>) -> Option<String> {
+    let mut variable: Option<String> = None;
+    for capture_part in capture.into_inner() {
+        match capture_part.as_rule() {
+            Rule::capture_variable => variable = Some(String::from(capture_part.as_str())),
+            _ => unreachable!(),
+        }
+    }
+    variable
+}
+
+fn parse_cmd_option(cmd_option: Pair<Rule>, options: &mut CommandOptions) -> Result<()> {
+    for option in cmd_option.into_inner() {
+        let rule = option.as_rule();
+        let values: Vec<&str> = option.into_inner().map(|value| value.as_str()).collect();
+        match rule {
+            Rule::cmd_timeout => options.timeout = Some(parse_duration(values[0])?),
+            Rule::cmd_retries => options.retries = Some(values[0].parse()?),
+            Rule::cmd_backoff => options.backoff = Some(parse_duration(values[0])?),
+            Rule::cmd_cwd => options.cwd = Some(String::from(values[0])),
+            Rule::cmd_env => options
+                .env
+                .push((String::from(values[0]), String::from(values[1]))),
+            _ => unreachable!(),
+        }
     }
+    Ok(())
 }
 
-fn modify_dest_entry(
-    entry: &mut Node,
-    dest_node: (String, Option<String>, Option<String>, Option<String>),
-) {
-    if dest_node.1.is_some() {
-        entry.label = dest_node.1;
+fn modify_src_entry(entry: &mut Node, src_node: NodeDecl, edge: EdgeDecl, dest_node: NodeDecl) {
+    entry.outputs.push(Edge {
+        destination: dest_node.0.clone(),
+        ..edge.1
+    });
+    // overwriting previous values to match mermaid :rolling-eyes:
+    modify_dest_entry(entry, src_node);
+}
+
+fn modify_dest_entry(entry: &mut Node, dest_node: NodeDecl) {
+    if dest_node.1.label.is_some() {
+        entry.label = dest_node.1.label;
+    }
+    if dest_node.1.cmd.is_some() {
+        entry.cmd = dest_node.1.cmd;
+    }
+    if dest_node.1.cb.is_some() {
+        entry.cb = dest_node.1.cb;
     }
-    if dest_node.2.is_some() {
-        entry.cmd = dest_node.2;
+    if dest_node.1.capture.is_some() {
+        entry.capture = dest_node.1.capture;
     }
-    if dest_node.3.is_some() {
-        entry.cb = dest_node.3;
+    if dest_node.1.cmd_options != CommandOptions::default() {
+        entry.cmd_options = dest_node.1.cmd_options;
     }
 }
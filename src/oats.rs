@@ -61,10 +61,7 @@ pub fn parse_oats(oats_string: &str, verbose: bool) -> Result<Vec<Groat>> {
                         _ => unreachable!(),
                     }
                 }
-                nodes.push(Groat {
-                    marker: marker,
-                    content: content,
-                });
+                nodes.push(Groat { marker, content });
             }
             Rule::clipboard_node => {
                 let mut content: Option<String> = None;
@@ -76,10 +73,7 @@ pub fn parse_oats(oats_string: &str, verbose: bool) -> Result<Vec<Groat>> {
                         _ => unreachable!(),
                     }
                 }
-                nodes.push(Groat {
-                    marker: marker,
-                    content: content,
-                });
+                nodes.push(Groat { marker, content });
             }
             Rule::breaker => nodes.push(Groat {
                 marker: Some(Marker::Breaker),
@@ -95,5 +89,5 @@ pub fn parse_oats(oats_string: &str, verbose: bool) -> Result<Vec<Groat>> {
         }
     }
 
-    return Ok(nodes);
+    Ok(nodes)
 }
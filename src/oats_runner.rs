@@ -61,7 +61,7 @@ impl Oatify for OatsMachine {
             .for_each(|sublist| match sublist[0].marker {
                 Marker::AndThen => {
                     sublist
-                        .into_iter()
+                        .iter()
                         .filter(|a| a.content.is_some())
                         .for_each(|step| {
                             let text: String;
@@ -95,7 +95,7 @@ impl Oatify for OatsMachine {
                 }
                 Marker::Optional => {
                     sublist
-                        .into_iter()
+                        .iter()
                         .filter(|a| a.content.is_some())
                         .for_each(|step| {
                             let mut text: String;
@@ -135,7 +135,7 @@ impl Oatify for OatsMachine {
                             .into_iter()
                             .filter(|a| !a.done && a.content.is_some())
                             .collect();
-                        if undone_unorders.len() <= 0 {
+                        if undone_unorders.is_empty() {
                             break;
                         };
                         let unordered_items: Vec<String> = undone_unorders
@@ -204,7 +204,8 @@ impl Oatify for OatsMachine {
 pub fn groats_to_oatlets(groats: &Vec<Groat>) -> Vec<Oatlet> {
     let mut oatlets: Vec<Oatlet> = Vec::new();
     for groat in groats {
-        if groat.marker.is_some() && groat.marker.unwrap() == Marker::Clipbo && oatlets.len() > 0 {
+        if groat.marker.is_some() && groat.marker.unwrap() == Marker::Clipbo && !oatlets.is_empty()
+        {
             match &oatlets.last().unwrap().clipboard {
                 Some(clip) => {
                     match &groat.content {
@@ -216,7 +217,11 @@ pub fn groats_to_oatlets(groats: &Vec<Groat>) -> Vec<Oatlet> {
                     }
                 }
                 None => {
-                    oatlets.last_mut().unwrap().clipboard = groat.content.clone();
+                    oatlets
+                        .last_mut()
+                        .unwrap()
+                        .clipboard
+                        .clone_from(&groat.content);
                 }
             }
         } else if groat.marker.is_some() {
@@ -228,7 +233,7 @@ pub fn groats_to_oatlets(groats: &Vec<Groat>) -> Vec<Oatlet> {
             });
         }
     }
-    return oatlets;
+    oatlets
 }
 
 fn copy_to_clipboard(cb_text: String) -> Result<()> {
@@ -0,0 +1,71 @@
+use anyhow::{bail, Result};
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Default)]
+pub struct Variables {
+    values: HashMap<String, String>,
+}
+
+impl Variables {
+    pub fn new() -> Variables {
+        Variables {
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.values.get(name).map(|value| value.as_str())
+    }
+
+    pub fn set(&mut self, name: &str, value: &str) {
+        self.values.insert(String::from(name), String::from(value));
+    }
+
+    pub fn is_set(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    /// Replaces `${NAME}` and `$NAME` with the value of a set variable, leaving
+    /// anything else (like the shell's own variables) untouched.
+    pub fn interpolate(&self, text: &str) -> String {
+        let mut interpolated = String::with_capacity(text.len());
+        let mut rest = text;
+        while let Some(dollar) = rest.find('$') {
+            interpolated.push_str(&rest[..dollar]);
+            let after = &rest[dollar + 1..];
+            let (name, reference_len) = match after.strip_prefix('{') {
+                Some(braced) => match braced.find('}') {
+                    Some(close) => (&braced[..close], close + 3),
+                    None => ("", 1),
+                },
+                None => {
+                    let name_len = after
+                        .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                        .unwrap_or(after.len());
+                    (&after[..name_len], name_len + 1)
+                }
+            };
+            match self.get(name) {
+                Some(value) if !name.is_empty() => interpolated.push_str(value),
+                _ => interpolated.push_str(&rest[dollar..dollar + reference_len]),
+            }
+            rest = &rest[dollar + reference_len..];
+        }
+        interpolated.push_str(rest);
+        interpolated
+    }
+}
+
+/// Parses a `NAME=value` assignment as given to `--var`.
+pub fn parse_assignment(assignment: &str) -> Result<(String, String)> {
+    match assignment.split_once('=') {
+        Some((name, value)) => {
+            let name = name.trim();
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                bail!("invalid variable name in {}", assignment);
+            }
+            Ok((String::from(name), String::from(value)))
+        }
+        None => bail!("expected NAME=value, got {}", assignment),
+    }
+}
@@ -7,7 +7,7 @@ mod tests {
         let ckl = r#"hello
     there
         "#;
-        let vec = parse_checklist(&ckl, false).unwrap();
+        let vec = parse_checklist(ckl, false).unwrap();
         assert_eq!(vec[0].alone, Some(String::from("hello")));
         assert_eq!(vec[1].alone, Some(String::from("there")));
     }
@@ -16,7 +16,7 @@ mod tests {
     fn before_and_after() {
         let ckl = r#"hello ```foo``` there
         "#;
-        let vec = parse_checklist(&ckl, false).unwrap();
+        let vec = parse_checklist(ckl, false).unwrap();
         assert_eq!(vec[0].before, Some(String::from("hello")));
         assert_eq!(vec[0].wrapper, Some(String::from("foo")));
         assert_eq!(vec[0].after, Some(String::from("there")));
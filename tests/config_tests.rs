@@ -0,0 +1,49 @@
+#[cfg(test)]
+mod tests {
+    use lanthir_cli::config::parse_config;
+    use lanthir_cli::duration::parse_duration;
+    use std::time::Duration;
+
+    #[test]
+    fn durations() {
+        assert_eq!(parse_duration("30").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("30s").unwrap(), Duration::from_secs(30));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("1h30m").unwrap(), Duration::from_secs(5400));
+        assert_eq!(parse_duration("250ms").unwrap(), Duration::from_millis(250));
+        assert!(parse_duration("soon").is_err());
+        assert!(parse_duration("5 minutes").is_err());
+    }
+
+    #[test]
+    fn command_options() {
+        let config_string = r#"# defaults for every run[...] node
+command.timeout = 5m
+command.retries = 2
+
+command.cwd = /srv/app
+command.env = RUST_LOG=info
+command.env = EMPTY=
+"#;
+        let config = parse_config(config_string).unwrap();
+        let options = config.command_options().unwrap();
+        assert_eq!(options.timeout, Some(Duration::from_secs(300)));
+        assert_eq!(options.retries, Some(2));
+        assert_eq!(options.backoff, None);
+        assert_eq!(options.cwd, Some(String::from("/srv/app")));
+        assert_eq!(
+            options.env,
+            vec![
+                (String::from("RUST_LOG"), String::from("info")),
+                (String::from("EMPTY"), String::new()),
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_values() {
+        let config = parse_config("command.retries = lots").unwrap();
+        assert!(config.command_options().is_err());
+        assert!(parse_config("no equals sign here").is_err());
+    }
+}
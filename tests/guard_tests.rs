@@ -0,0 +1,44 @@
+#[cfg(test)]
+mod tests {
+    use lanthir_cli::guard::{Condition, Guard};
+    use lanthir_cli::vars::Variables;
+
+    #[test]
+    fn equality() {
+        let guard = Guard {
+            clauses: vec![vec![Condition::Equals(
+                String::from("env"),
+                String::from("prod"),
+            )]],
+        };
+        let mut vars = Variables::new();
+        assert!(!guard.evaluate(&vars));
+        vars.set("env", "prod");
+        assert!(guard.evaluate(&vars));
+        vars.set("env", "staging");
+        assert!(!guard.evaluate(&vars));
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let guard = Guard {
+            clauses: vec![
+                vec![
+                    Condition::Equals(String::from("env"), String::from("prod")),
+                    Condition::Falsy(String::from("dry_run")),
+                ],
+                vec![Condition::Truthy(String::from("force"))],
+            ],
+        };
+        let mut vars = Variables::new();
+        vars.set("env", "prod");
+        vars.set("dry_run", "true");
+        assert!(!guard.evaluate(&vars));
+        vars.set("force", "yes");
+        assert!(guard.evaluate(&vars));
+        vars.set("force", "false");
+        vars.set("dry_run", "0");
+        assert!(guard.evaluate(&vars));
+        assert_eq!(guard.variables(), vec!["env", "dry_run", "force"]);
+    }
+}
@@ -1,5 +1,7 @@
 #[cfg(test)]
 mod tests {
+    use lanthir_cli::guard::Condition;
+    use std::time::Duration;
     use lanthir_cli::mermaid::parse_mermaid;
 
     #[test]
@@ -7,7 +9,7 @@ mod tests {
         let mmd = r#"flowchart TD
     A-->B
         "#;
-        let graph = parse_mermaid(&mmd, false).unwrap();
+        let graph = parse_mermaid(mmd, false).unwrap();
         assert_eq!(graph["A"].label, None);
         assert_eq!(graph["A"].outputs[0].destination, "B");
     }
@@ -17,7 +19,7 @@ mod tests {
         let mmd = r#"flowchart TD
     A[apple]-->|foo|B{banana}
         "#;
-        let graph = parse_mermaid(&mmd, false).unwrap();
+        let graph = parse_mermaid(mmd, false).unwrap();
         assert_eq!(graph["A"].label, Some(String::from("apple")));
         assert_eq!(graph["B"].label, Some(String::from("banana")));
         assert_eq!(graph["A"].outputs[0].label, Some(String::from("foo")));
@@ -29,7 +31,7 @@ mod tests {
         let mmd = r#"flowchart TD
     A--Torture Test-->B("run[-_(){}'~./\|&;<>$`,:@%^*+=?{}!]")
         "#;
-        let graph = parse_mermaid(&mmd, false).unwrap();
+        let graph = parse_mermaid(mmd, false).unwrap();
         assert_eq!(graph["A"].label, None);
         assert_eq!(graph["B"].label, None);
         assert_eq!(
@@ -48,11 +50,110 @@ mod tests {
         let mmd = r#"flowchart TD
     A--CMD Test-->B["run[echo #quot;hello#quot;]"]
         "#;
-        let graph = parse_mermaid(&mmd, false).unwrap();
+        let graph = parse_mermaid(mmd, false).unwrap();
         assert_eq!(graph["A"].label, None);
         assert_eq!(graph["B"].label, None);
         assert_eq!(graph["B"].cmd, Some(String::from("echo \"hello\"")));
         assert_eq!(graph["A"].outputs[0].label, Some(String::from("CMD Test")));
         assert_eq!(graph["A"].outputs[0].destination, "B");
     }
+
+    #[test]
+    fn edge_limits() {
+        let mmd = r#"flowchart TD
+    A-->|retry max 3|B
+    B-- try again max 2 -->A
+    B-->|max 1|C
+    B-->|maximum effort|D
+        "#;
+        let graph = parse_mermaid(mmd, false).unwrap();
+        assert_eq!(graph["A"].outputs[0].label, Some(String::from("retry")));
+        assert_eq!(graph["A"].outputs[0].limit, Some(3));
+        assert_eq!(graph["B"].outputs[0].label, Some(String::from("try again")));
+        assert_eq!(graph["B"].outputs[0].limit, Some(2));
+        assert_eq!(graph["B"].outputs[1].label, None);
+        assert_eq!(graph["B"].outputs[1].limit, Some(1));
+        assert_eq!(
+            graph["B"].outputs[2].label,
+            Some(String::from("maximum effort"))
+        );
+        assert_eq!(graph["B"].outputs[2].limit, None);
+    }
+
+    #[test]
+    fn edge_guards() {
+        let mmd = r#"flowchart TD
+    A-->|if env == prod|B
+    A-->|if env != prod and not dry_run or force max 2|C
+    A-->|else|D
+    A-->|iffy|E
+        "#;
+        let graph = parse_mermaid(mmd, false).unwrap();
+        let prod = graph["A"].outputs[0].guard.as_ref().unwrap();
+        assert_eq!(
+            prod.clauses,
+            vec![vec![Condition::Equals(
+                String::from("env"),
+                String::from("prod")
+            )]]
+        );
+        assert_eq!(
+            graph["A"].outputs[0].label,
+            Some(String::from("if env == prod"))
+        );
+        let staging = graph["A"].outputs[1].guard.as_ref().unwrap();
+        assert_eq!(
+            staging.clauses,
+            vec![
+                vec![
+                    Condition::NotEquals(String::from("env"), String::from("prod")),
+                    Condition::Falsy(String::from("dry_run")),
+                ],
+                vec![Condition::Truthy(String::from("force"))],
+            ]
+        );
+        assert_eq!(
+            graph["A"].outputs[1].label,
+            Some(String::from("if env != prod and not dry_run or force"))
+        );
+        assert_eq!(graph["A"].outputs[1].limit, Some(2));
+        assert!(graph["A"].outputs[2].guard.is_none());
+        assert!(graph["A"].outputs[3].guard.is_none());
+        assert_eq!(graph["A"].outputs[3].label, Some(String::from("iffy")));
+    }
+
+    #[test]
+    fn capture() {
+        let mmd = r#"flowchart TD
+    A["run[kubectl get pod -o name] as POD"]-->B["run[kubectl logs ${POD}]"]
+        "#;
+        let graph = parse_mermaid(mmd, false).unwrap();
+        assert_eq!(
+            graph["A"].cmd,
+            Some(String::from("kubectl get pod -o name"))
+        );
+        assert_eq!(graph["A"].capture, Some(String::from("POD")));
+        assert_eq!(graph["B"].cmd, Some(String::from("kubectl logs ${POD}")));
+        assert_eq!(graph["B"].capture, None);
+    }
+
+    #[test]
+    fn command_options() {
+        let mmd = r#"flowchart TD
+    A["run[make deploy] timeout 5m retries 2 as OUT cwd /srv/app env STAGE=prod"]-->|success|B
+    A-->|failure|C
+        "#;
+        let graph = parse_mermaid(mmd, false).unwrap();
+        assert_eq!(graph["A"].cmd, Some(String::from("make deploy")));
+        assert_eq!(graph["A"].capture, Some(String::from("OUT")));
+        let options = &graph["A"].cmd_options;
+        assert_eq!(options.timeout, Some(Duration::from_secs(300)));
+        assert_eq!(options.retries, Some(2));
+        assert_eq!(options.cwd, Some(String::from("/srv/app")));
+        assert_eq!(
+            options.env,
+            vec![(String::from("STAGE"), String::from("prod"))]
+        );
+        assert_eq!(graph["A"].outputs[0].label, Some(String::from("success")));
+    }
 }
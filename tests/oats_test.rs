@@ -7,7 +7,7 @@ mod tests {
     fn and_then() {
         let oats = r#"~ hello
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::AndThen));
         assert_eq!(grains[0].content, Some(String::from("hello")));
     }
@@ -16,7 +16,7 @@ mod tests {
     fn optional() {
         let oats = r#"? hello
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::Optional));
         assert_eq!(grains[0].content, Some(String::from("hello")));
     }
@@ -25,7 +25,7 @@ mod tests {
     fn one_of() {
         let oats = r#"| hello
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::OneOf));
         assert_eq!(grains[0].content, Some(String::from("hello")));
     }
@@ -34,7 +34,7 @@ mod tests {
     fn unordered() {
         let oats = r#"& hello
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::Unordered));
         assert_eq!(grains[0].content, Some(String::from("hello")));
     }
@@ -50,7 +50,7 @@ mod tests {
     ~ bar
     ? baz
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::Unordered));
         assert_eq!(grains[0].content, Some(String::from("hello")));
         assert_eq!(grains[1].marker, Some(Marker::Unordered));
@@ -78,7 +78,7 @@ mod tests {
 = clippy 1
 =  clippy 2 // not a comment 
         "#;
-        let grains = parse_oats(&oats, false).unwrap();
+        let grains = parse_oats(oats, false).unwrap();
         assert_eq!(grains[0].marker, Some(Marker::Unordered));
         assert_eq!(grains[0].content, Some(String::from("hello")));
         assert_eq!(grains[1].marker, Some(Marker::Unordered));
@@ -113,12 +113,12 @@ mod tests {
 = clippy 1
 =  clippy 2 // not a comment 
         "#;
-        let groats: Vec<Groat> = parse_oats(&oats, false).unwrap();
+        let groats: Vec<Groat> = parse_oats(oats, false).unwrap();
         let oatlets: Vec<Oatlet> = groats_to_oatlets(&groats);
         assert_eq!(oatlets[0].marker, Marker::Unordered);
         assert_eq!(oatlets[0].content, Some(String::from("hello")));
         assert_eq!(oatlets[0].clipboard, None);
-        assert_eq!(oatlets[0].done, false);
+        assert!(!oatlets[0].done);
         assert_eq!(oatlets[1].marker, Marker::Unordered);
         assert_eq!(oatlets[1].content, Some(String::from("there")));
         assert_eq!(oatlets[1].clipboard, Some(String::from("blah blah")));
@@ -0,0 +1,30 @@
+#[cfg(test)]
+mod tests {
+    use lanthir_cli::vars::{parse_assignment, Variables};
+
+    #[test]
+    fn assignments() {
+        assert_eq!(
+            parse_assignment("env=prod").unwrap(),
+            (String::from("env"), String::from("prod"))
+        );
+        assert_eq!(
+            parse_assignment("query=a=b").unwrap(),
+            (String::from("query"), String::from("a=b"))
+        );
+        assert!(parse_assignment("no value").is_err());
+        assert!(parse_assignment("bad name=1").is_err());
+    }
+
+    #[test]
+    fn interpolation() {
+        let mut vars = Variables::new();
+        vars.set("POD", "web-1");
+        assert_eq!(
+            vars.interpolate("kubectl logs $POD --since ${SINCE} > ${POD}.log"),
+            "kubectl logs web-1 --since ${SINCE} > web-1.log"
+        );
+        assert_eq!(vars.interpolate("$HOME/$ and ${"), "$HOME/$ and ${");
+        assert_eq!(vars.interpolate("$POD_NAME"), "$POD_NAME");
+    }
+}